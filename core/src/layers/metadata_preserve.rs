@@ -0,0 +1,382 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crate::raw::*;
+use crate::*;
+
+/// The reserved `user_metadata` key this layer uses to round-trip a unix
+/// permission mode, mirroring how tar and pxar carry `st_mode` alongside
+/// regular extended attributes.
+const MODE_KEY: &str = "unix_mode";
+
+/// The reserved `user_metadata` key this layer uses to round-trip a
+/// last-modified time. We deliberately don't compare against the
+/// destination's native `last_modified`: every backend stamps that with
+/// the time of the copy/rename itself, so it is never equal to the
+/// source's value and isn't something this layer can set via `write`.
+/// The source's timestamp is stashed under its own key instead. Unlike
+/// `unix_mode`, this key is not checked on `stat` and is written to the
+/// destination only when a re-attach happens because some other
+/// preserved attribute was dropped.
+const MTIME_KEY: &str = "unix_mtime";
+
+/// Preserve a source object's user metadata, unix mode, and
+/// last-modified time across `copy` and `rename`.
+///
+/// Many services don't carry arbitrary metadata through a server-side
+/// copy the way a tar or pxar extractor preserves `unpack_xattrs`,
+/// `preserve_permissions`, and `preserve_mtime` on unpack. This layer
+/// only acts when the inner service advertises
+/// `Capability::write_with_user_metadata`; on other services it's a
+/// pure passthrough. When active, it snapshots the configured attribute
+/// classes from the source before delegating `copy`/`rename`, and
+/// records what the destination is expected to carry. The actual
+/// validation — and, if the native operation dropped something, a
+/// single read-then-write re-attachment — happens the next time `stat`
+/// is called on that destination, so a caller who never stats the
+/// result never pays for the extra round trips. If the attributes are
+/// still missing after re-attaching, it reports an `Unexpected`
+/// diagnostic the same way the sanity-check layer does. The
+/// last-modified time is not part of this validation; see `MTIME_KEY`.
+pub struct MetadataPreserveLayer {
+    preserve_user_metadata: bool,
+    preserve_mode: bool,
+    preserve_mtime: bool,
+}
+
+impl Default for MetadataPreserveLayer {
+    fn default() -> Self {
+        Self {
+            preserve_user_metadata: true,
+            preserve_mode: true,
+            preserve_mtime: true,
+        }
+    }
+}
+
+impl MetadataPreserveLayer {
+    /// Create a layer that preserves every supported attribute class.
+    /// Use the `with_*` methods to opt out of classes the inner service
+    /// doesn't support.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Toggle preservation of the `user_metadata` key/value map.
+    pub fn with_user_metadata(mut self, enabled: bool) -> Self {
+        self.preserve_user_metadata = enabled;
+        self
+    }
+
+    /// Toggle preservation of the unix permission mode.
+    pub fn with_mode(mut self, enabled: bool) -> Self {
+        self.preserve_mode = enabled;
+        self
+    }
+
+    /// Toggle preservation of the last-modified timestamp.
+    pub fn with_mtime(mut self, enabled: bool) -> Self {
+        self.preserve_mtime = enabled;
+        self
+    }
+}
+
+impl<A: Access> Layer<A> for MetadataPreserveLayer {
+    type LayeredAccess = MetadataPreserveAccessor<A>;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        let info = inner.info();
+        MetadataPreserveAccessor {
+            info,
+            preserve_user_metadata: self.preserve_user_metadata,
+            preserve_mode: self.preserve_mode,
+            preserve_mtime: self.preserve_mtime,
+            pending: Mutex::new(HashMap::new()),
+            inner,
+        }
+    }
+}
+
+pub struct MetadataPreserveAccessor<A: Access> {
+    info: Arc<AccessorInfo>,
+    preserve_user_metadata: bool,
+    preserve_mode: bool,
+    preserve_mtime: bool,
+    /// Attributes a `copy`/`rename` expects its destination to carry,
+    /// keyed by destination path. `stat` consumes (and validates) an
+    /// entry the first time it sees that path afterwards.
+    pending: Mutex<HashMap<String, HashMap<String, String>>>,
+    inner: A,
+}
+
+impl<A: Access> Debug for MetadataPreserveAccessor<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetadataPreserveAccessor")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: Access> MetadataPreserveAccessor<A> {
+    /// Whether this layer has anything to do at all: at least one
+    /// attribute class is enabled and the inner service can actually
+    /// carry user metadata on `write`.
+    fn should_preserve(&self) -> bool {
+        let wants_any = self.preserve_user_metadata || self.preserve_mode || self.preserve_mtime;
+        wants_any && self.info.native_capability().write_with_user_metadata
+    }
+
+    fn capture(&self, meta: &Metadata) -> HashMap<String, String> {
+        let mut preserved = if self.preserve_user_metadata {
+            meta.user_metadata().cloned().unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
+
+        if self.preserve_mode {
+            if let Some(mode) = meta.user_metadata().and_then(|m| m.get(MODE_KEY)) {
+                preserved.insert(MODE_KEY.to_string(), mode.clone());
+            }
+        } else {
+            preserved.remove(MODE_KEY);
+        }
+
+        if self.preserve_mtime {
+            if let Some(mtime) = meta.last_modified() {
+                preserved.insert(MTIME_KEY.to_string(), mtime.timestamp().to_string());
+            }
+        } else {
+            preserved.remove(MTIME_KEY);
+        }
+
+        preserved
+    }
+
+    /// Record that `to` is expected to carry `preserved` the next time
+    /// it's stat'd. A no-op `preserved` records nothing, so `stat` on an
+    /// untouched path never takes the pending-check branch.
+    fn expect(&self, to: &str, preserved: HashMap<String, String>) {
+        if preserved.is_empty() {
+            return;
+        }
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(to.to_string(), preserved);
+    }
+
+    /// Re-attach `preserved` to `path` with a single read-then-write
+    /// round trip, used when the native copy/rename didn't already
+    /// carry it over.
+    async fn reattach(&self, path: &str, preserved: &HashMap<String, String>) -> Result<()> {
+        let (_, mut reader) = self.inner.read(path, OpRead::new()).await?;
+        let mut buf = Vec::new();
+        loop {
+            let chunk = reader.read().await?;
+            if chunk.is_empty() {
+                break;
+            }
+            buf.extend_from_slice(&chunk.to_bytes());
+        }
+
+        let (_, mut writer) = self
+            .inner
+            .write(path, OpWrite::new().with_user_metadata(preserved.clone()))
+            .await?;
+        writer.write(Buffer::from(buf)).await?;
+        writer.close().await?;
+
+        Ok(())
+    }
+}
+
+/// Whether `meta` already carries everything in `preserved`, for the
+/// purpose of deciding whether a reattach rewrite is needed.
+///
+/// `unix_mtime` is deliberately excluded from this decision: it's a
+/// synthetic key that only our own `reattach` ever writes, so no native
+/// `copy`/`rename` will ever echo it back on its own. Treating it as
+/// "dropped" would make every mtime-preserving copy take the rewrite
+/// path even when the native operation faithfully carried everything
+/// else over. When a rewrite does happen for another reason, the mtime
+/// key rides along in that same write.
+fn attributes_present(meta: &Metadata, preserved: &HashMap<String, String>) -> bool {
+    let actual = meta.user_metadata();
+    preserved
+        .iter()
+        .filter(|(key, _)| key.as_str() != MTIME_KEY)
+        .all(|(key, value)| actual.and_then(|m| m.get(key)).is_some_and(|v| v == value))
+}
+
+impl<A: Access> LayeredAccess for MetadataPreserveAccessor<A> {
+    type Inner = A;
+    type Reader = A::Reader;
+    type Writer = A::Writer;
+    type Lister = A::Lister;
+    type Deleter = A::Deleter;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    fn info(&self) -> Arc<AccessorInfo> {
+        self.info.clone()
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        let rp = self.inner.stat(path, args).await?;
+
+        let Some(preserved) = self.pending.lock().unwrap().remove(path) else {
+            return Ok(rp);
+        };
+
+        if attributes_present(rp.metadata(), &preserved) {
+            return Ok(rp);
+        }
+
+        self.reattach(path, &preserved).await?;
+
+        let refreshed = self.inner.stat(path, OpStat::new()).await?;
+        if !attributes_present(refreshed.metadata(), &preserved) {
+            return Err(preserve_diagnostic(
+                &self.info,
+                Operation::Stat,
+                path,
+                "preserved attributes were still missing after re-attaching them",
+            ));
+        }
+
+        Ok(refreshed)
+    }
+
+    async fn copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        if !self.should_preserve() {
+            return self.inner.copy(from, to, args).await;
+        }
+
+        let source = self.inner.stat(from, OpStat::new()).await?.into_metadata();
+        let preserved = self.capture(&source);
+
+        let rp = self.inner.copy(from, to, args).await?;
+        self.expect(to, preserved);
+
+        Ok(rp)
+    }
+
+    async fn rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        if !self.should_preserve() {
+            return self.inner.rename(from, to, args).await;
+        }
+
+        // Capture before renaming: `from` won't exist to stat afterwards.
+        let source = self.inner.stat(from, OpStat::new()).await?.into_metadata();
+        let preserved = self.capture(&source);
+
+        let rp = self.inner.rename(from, to, args).await?;
+        self.expect(to, preserved);
+
+        Ok(rp)
+    }
+}
+
+fn preserve_diagnostic(
+    info: &AccessorInfo,
+    op: Operation,
+    path: &str,
+    detail: impl Into<String>,
+) -> Error {
+    Error::new(
+        ErrorKind::Unexpected,
+        format!(
+            "service {} dropped preserved metadata during {} response: {}",
+            info.scheme(),
+            op,
+            detail.into()
+        ),
+    )
+    .with_operation(op)
+    .with_context("path", path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta_with_user_metadata(pairs: &[(&str, &str)]) -> Metadata {
+        let mut meta = Metadata::new(EntryMode::FILE);
+        let map = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        meta.set_user_metadata(map);
+        meta
+    }
+
+    #[test]
+    fn attributes_present_accepts_superset() {
+        let preserved = HashMap::from([("owner".to_string(), "alice".to_string())]);
+        let destination = meta_with_user_metadata(&[("owner", "alice"), ("extra", "1")]);
+        assert!(attributes_present(&destination, &preserved));
+    }
+
+    #[test]
+    fn attributes_present_rejects_dropped_key() {
+        let preserved = HashMap::from([("owner".to_string(), "alice".to_string())]);
+        let destination = Metadata::new(EntryMode::FILE);
+        assert!(!attributes_present(&destination, &preserved));
+    }
+
+    #[test]
+    fn attributes_present_rejects_dropped_mode() {
+        let preserved = HashMap::from([(MODE_KEY.to_string(), "0755".to_string())]);
+        let destination = Metadata::new(EntryMode::FILE);
+        assert!(!attributes_present(&destination, &preserved));
+    }
+
+    #[test]
+    fn attributes_present_is_vacuously_true_when_nothing_preserved() {
+        let preserved = HashMap::new();
+        let destination = Metadata::new(EntryMode::FILE);
+        assert!(attributes_present(&destination, &preserved));
+    }
+
+    #[test]
+    fn attributes_present_ignores_mtime_since_no_backend_sets_it_natively() {
+        // A faithful native copy carries `owner` over but, as always,
+        // never sets our synthetic `unix_mtime` key on its own. That must
+        // not be treated as "dropped" and force a reattach rewrite.
+        let preserved = HashMap::from([
+            ("owner".to_string(), "alice".to_string()),
+            (MTIME_KEY.to_string(), "1700000000".to_string()),
+        ]);
+        let destination = meta_with_user_metadata(&[("owner", "alice")]);
+        assert!(attributes_present(&destination, &preserved));
+    }
+
+    #[test]
+    fn attributes_present_is_vacuously_true_for_mtime_only() {
+        let preserved = HashMap::from([(MTIME_KEY.to_string(), "1700000000".to_string())]);
+        let destination = Metadata::new(EntryMode::FILE);
+        assert!(attributes_present(&destination, &preserved));
+    }
+}
@@ -0,0 +1,431 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use crate::raw::oio;
+use crate::raw::*;
+use crate::*;
+
+/// A mask of the operations a [`PermissionRule`] applies to.
+///
+/// Masks compose with `|`, e.g. `OperationMask::READ | OperationMask::LIST`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationMask(u8);
+
+impl OperationMask {
+    pub const READ: Self = Self(1 << 0);
+    pub const WRITE: Self = Self(1 << 1);
+    pub const DELETE: Self = Self(1 << 2);
+    pub const LIST: Self = Self(1 << 3);
+    pub const STAT: Self = Self(1 << 4);
+    pub const ALL: Self = Self(
+        Self::READ.0 | Self::WRITE.0 | Self::DELETE.0 | Self::LIST.0 | Self::STAT.0,
+    );
+
+    fn contains(&self, op: Operation) -> bool {
+        let bit = match op {
+            Operation::Read => Self::READ,
+            Operation::Write => Self::WRITE,
+            Operation::Delete => Self::DELETE,
+            Operation::List => Self::LIST,
+            Operation::Stat => Self::STAT,
+            // Copy, rename, and create_dir all create/overwrite something
+            // at the destination, so they're governed by the write mask.
+            Operation::Copy | Operation::Rename | Operation::CreateDir => Self::WRITE,
+            _ => return false,
+        };
+        self.0 & bit.0 != 0
+    }
+}
+
+impl std::ops::BitOr for OperationMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[derive(Clone)]
+enum PathMatcher {
+    Prefix(String),
+    Glob(String),
+}
+
+impl PathMatcher {
+    fn new(pattern: impl Into<String>) -> Self {
+        let pattern = pattern.into();
+        if pattern.contains('*') {
+            PathMatcher::Glob(pattern)
+        } else {
+            PathMatcher::Prefix(pattern)
+        }
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        match self {
+            PathMatcher::Prefix(prefix) => path.starts_with(prefix.as_str()),
+            PathMatcher::Glob(pattern) => glob_match(pattern, path),
+        }
+    }
+}
+
+/// A minimal `*`-only glob matcher: each `*` greedily matches any run of
+/// characters (including none), everything else must match literally.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let segments: Vec<&str> = pattern.split('*').collect();
+    if segments.len() == 1 {
+        return pattern == path;
+    }
+
+    let mut cursor = 0;
+    for (i, segment) in segments.iter().enumerate() {
+        if segment.is_empty() {
+            continue;
+        }
+        if i == 0 {
+            if !path[cursor..].starts_with(segment) {
+                return false;
+            }
+            cursor += segment.len();
+        } else if i == segments.len() - 1 {
+            return path[cursor..].ends_with(segment);
+        } else if let Some(found) = path[cursor..].find(segment) {
+            cursor += found + segment.len();
+        } else {
+            return false;
+        }
+    }
+    true
+}
+
+#[derive(Clone)]
+enum PermissionEffect {
+    Allow,
+    Deny,
+}
+
+#[derive(Clone)]
+struct PermissionRule {
+    matcher: PathMatcher,
+    operations: OperationMask,
+    effect: PermissionEffect,
+}
+
+/// Gate every accessor call against an ordered set of path/operation
+/// allow and deny rules, in the spirit of Deno's filesystem permission
+/// model: access is denied by default, a rule must explicitly allow it,
+/// and any matching deny rule wins regardless of order.
+///
+/// ```ignore
+/// let layer = PermissionLayer::new()
+///     .allow("public/", OperationMask::READ | OperationMask::LIST)
+///     .deny("*", OperationMask::DELETE);
+/// ```
+///
+/// This grants read-only access under `public/` while blocking `delete`
+/// everywhere, giving multi-tenant or sandboxed embeddings a first-class
+/// enforcement point instead of each caller hand-rolling checks.
+pub struct PermissionLayer {
+    rules: Vec<PermissionRule>,
+}
+
+impl PermissionLayer {
+    /// Create a layer with no rules. Every operation is denied until an
+    /// `allow` rule is added.
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Allow `operations` on paths matching `pattern`, which is matched
+    /// as a glob if it contains `*`, otherwise as a path prefix.
+    pub fn allow(mut self, pattern: impl Into<String>, operations: OperationMask) -> Self {
+        self.rules.push(PermissionRule {
+            matcher: PathMatcher::new(pattern),
+            operations,
+            effect: PermissionEffect::Allow,
+        });
+        self
+    }
+
+    /// Deny `operations` on paths matching `pattern`. Deny rules always
+    /// take precedence over `allow` rules, regardless of the order they
+    /// were added in.
+    pub fn deny(mut self, pattern: impl Into<String>, operations: OperationMask) -> Self {
+        self.rules.push(PermissionRule {
+            matcher: PathMatcher::new(pattern),
+            operations,
+            effect: PermissionEffect::Deny,
+        });
+        self
+    }
+}
+
+impl Default for PermissionLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Access> Layer<A> for PermissionLayer {
+    type LayeredAccess = PermissionAccessor<A>;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        let info = inner.info();
+        PermissionAccessor {
+            info,
+            rules: Arc::new(self.rules.clone()),
+            inner,
+        }
+    }
+}
+
+pub struct PermissionAccessor<A: Access> {
+    info: Arc<AccessorInfo>,
+    rules: Arc<Vec<PermissionRule>>,
+    inner: A,
+}
+
+impl<A: Access> Debug for PermissionAccessor<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PermissionAccessor")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: Access> PermissionAccessor<A> {
+    fn check(&self, op: Operation, path: &str) -> Result<()> {
+        check_permission(&self.rules, &self.info, op, path)
+    }
+}
+
+fn check_permission(
+    rules: &[PermissionRule],
+    info: &AccessorInfo,
+    op: Operation,
+    path: &str,
+) -> Result<()> {
+    let mut allowed = false;
+    for rule in rules {
+        if rule.operations.contains(op) && rule.matcher.matches(path) {
+            match rule.effect {
+                PermissionEffect::Deny => return Err(permission_denied(info, op, path)),
+                PermissionEffect::Allow => allowed = true,
+            }
+        }
+    }
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(permission_denied(info, op, path))
+    }
+}
+
+fn permission_denied(info: &AccessorInfo, op: Operation, path: &str) -> Error {
+    Error::new(
+        ErrorKind::PermissionDenied,
+        format!(
+            "service {} denied {} on `{}` by permission layer rules",
+            info.scheme(),
+            op,
+            path
+        ),
+    )
+    .with_operation(op)
+    .with_context("path", path)
+}
+
+impl<A: Access> LayeredAccess for PermissionAccessor<A> {
+    type Inner = A;
+    type Reader = A::Reader;
+    type Writer = A::Writer;
+    type Lister = A::Lister;
+    type Deleter = PermissionDeleter<A::Deleter>;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    fn info(&self) -> Arc<AccessorInfo> {
+        self.info.clone()
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        self.check(Operation::Read, path)?;
+        self.inner.read(path, args).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        self.check(Operation::Write, path)?;
+        self.inner.write(path, args).await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        self.check(Operation::Stat, path)?;
+        self.inner.stat(path, args).await
+    }
+
+    async fn delete(&self) -> Result<(RpDelete, Self::Deleter)> {
+        let (rp, deleter) = self.inner.delete().await?;
+        Ok((
+            rp,
+            PermissionDeleter {
+                info: self.info.clone(),
+                rules: self.rules.clone(),
+                inner: deleter,
+            },
+        ))
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Lister)> {
+        self.check(Operation::List, path)?;
+        self.inner.list(path, args).await
+    }
+
+    async fn copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        // A copy reads `from` as much as it writes `to` — a rule set that
+        // grants WRITE but withholds READ on the source must still block
+        // it, or content can be copied out through a write-only grant.
+        self.check(Operation::Read, from)?;
+        self.check(Operation::Copy, to)?;
+        self.inner.copy(from, to, args).await
+    }
+
+    async fn rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        self.check(Operation::Read, from)?;
+        self.check(Operation::Rename, to)?;
+        self.inner.rename(from, to, args).await
+    }
+
+    async fn create_dir(&self, path: &str, args: OpCreateDir) -> Result<RpCreateDir> {
+        self.check(Operation::CreateDir, path)?;
+        self.inner.create_dir(path, args).await
+    }
+
+    async fn presign(&self, path: &str, args: OpPresign) -> Result<RpPresign> {
+        // A presigned URL grants whatever it's presigned for, so gate it on
+        // the operation it's actually standing in for rather than a fixed
+        // mask — a presigned write is as good as a write.
+        let op = match args.op() {
+            PresignOperation::Stat(_) => Operation::Stat,
+            PresignOperation::Read(_) => Operation::Read,
+            PresignOperation::Write(_) => Operation::Write,
+        };
+        self.check(op, path)?;
+        self.inner.presign(path, args).await
+    }
+}
+
+pub struct PermissionDeleter<D> {
+    info: Arc<AccessorInfo>,
+    rules: Arc<Vec<PermissionRule>>,
+    inner: D,
+}
+
+impl<D: oio::Delete> oio::Delete for PermissionDeleter<D> {
+    fn delete(&mut self, path: &str, args: OpDelete) -> Result<()> {
+        check_permission(&self.rules, &self.info, Operation::Delete, path)?;
+        self.inner.delete(path, args)
+    }
+
+    async fn flush(&mut self) -> Result<usize> {
+        self.inner.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_info() -> AccessorInfo {
+        let info = AccessorInfo::default();
+        info.set_scheme("test");
+        info
+    }
+
+    fn rules(layer: PermissionLayer) -> Vec<PermissionRule> {
+        layer.rules
+    }
+
+    #[test]
+    fn denies_by_default() {
+        let info = build_info();
+        let rules = rules(PermissionLayer::new());
+        let err = check_permission(&rules, &info, Operation::Read, "public/file")
+            .expect_err("unmatched path should be denied");
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn allows_matching_prefix() {
+        let info = build_info();
+        let rules = rules(PermissionLayer::new().allow("public/", OperationMask::READ));
+        check_permission(&rules, &info, Operation::Read, "public/file")
+            .expect("allowed prefix should pass");
+    }
+
+    #[test]
+    fn allow_does_not_cover_other_operations() {
+        let info = build_info();
+        let rules = rules(PermissionLayer::new().allow("public/", OperationMask::READ));
+        let err = check_permission(&rules, &info, Operation::Delete, "public/file")
+            .expect_err("delete should still be denied");
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn deny_overrides_allow_regardless_of_order() {
+        let info = build_info();
+        let rules = rules(
+            PermissionLayer::new()
+                .allow("*", OperationMask::ALL)
+                .deny("secret/", OperationMask::READ),
+        );
+        let err = check_permission(&rules, &info, Operation::Read, "secret/file")
+            .expect_err("deny should win even though allow matched too");
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+
+        check_permission(&rules, &info, Operation::Read, "public/file")
+            .expect("paths outside the deny rule stay allowed");
+    }
+
+    #[test]
+    fn copy_source_requires_read_not_just_write() {
+        // Mirrors what `PermissionAccessor::copy` checks: a write-only
+        // grant on the source must not be enough to read it out via copy.
+        let info = build_info();
+        let rules = rules(PermissionLayer::new().allow("src/", OperationMask::WRITE));
+        let err = check_permission(&rules, &info, Operation::Read, "src/file")
+            .expect_err("write-only grant on the source should not permit reading it via copy");
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn glob_pattern_matches_middle_wildcard() {
+        let info = build_info();
+        let rules = rules(PermissionLayer::new().allow("logs/*.txt", OperationMask::READ));
+        check_permission(&rules, &info, Operation::Read, "logs/2024/app.txt")
+            .expect("glob should match across path segments");
+        check_permission(&rules, &info, Operation::Read, "logs/app.csv")
+            .expect_err("glob should not match a different suffix");
+    }
+}
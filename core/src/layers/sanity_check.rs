@@ -32,6 +32,12 @@ use crate::*;
 /// reporting a directory entry without a trailing slash), OpenDAL
 /// will now return an `Unexpected` error instead of continuing with
 /// potentially undefined behaviour.
+///
+/// It also checks `read` against the byte count the service promised
+/// through `RpRead::size`, returning an `Unexpected` error if the stream
+/// ends short or yields more bytes than that. When `RpRead::size` is not
+/// reported, the requested `Range` length only caps the read: yielding
+/// more is an error, ending short is not.
 #[derive(Default)]
 pub struct SanityCheckLayer;
 
@@ -59,7 +65,7 @@ impl<A: Access> Debug for SanityCheckAccessor<A> {
 
 impl<A: Access> LayeredAccess for SanityCheckAccessor<A> {
     type Inner = A;
-    type Reader = A::Reader;
+    type Reader = SanityCheckReader<A::Reader>;
     type Writer = A::Writer;
     type Lister = SanityCheckLister<A::Lister>;
     type Deleter = A::Deleter;
@@ -73,7 +79,27 @@ impl<A: Access> LayeredAccess for SanityCheckAccessor<A> {
     }
 
     async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
-        self.inner.read(path, args).await
+        // A bounded range can legitimately exceed the object's actual size
+        // (the service is expected to clamp it and return fewer bytes), so
+        // it can only ever serve as an upper bound on how much we should
+        // see, never as a mandatory minimum. `RpRead::size` is the only
+        // authority for detecting a short read, since it reflects what the
+        // service says it's about to send for *this* request.
+        let range_upper_bound = args.range().size();
+        let (rp, reader) = self.inner.read(path, args).await?;
+        let expected_size = rp.size();
+
+        Ok((
+            rp,
+            SanityCheckReader {
+                info: self.info.clone(),
+                path: path.to_string(),
+                expected_size,
+                range_upper_bound,
+                consumed: 0,
+                inner: reader,
+            },
+        ))
     }
 
     async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
@@ -99,6 +125,83 @@ impl<A: Access> LayeredAccess for SanityCheckAccessor<A> {
     }
 }
 
+/// Wraps a reader to make sure the number of bytes it actually yields
+/// matches what the service promised.
+///
+/// `expected_size` (from `RpRead::size`) is authoritative and checked for
+/// both a short read and an overrun. `range_upper_bound` (the length of
+/// the requested `Range`, as asked for) only ever bounds the read from
+/// above: a service is allowed to return fewer bytes than a bounded
+/// range asked for when the range extends past the object's actual
+/// size, so it's only used to catch an overrun when `expected_size`
+/// wasn't reported at all.
+pub struct SanityCheckReader<R> {
+    info: Arc<AccessorInfo>,
+    path: String,
+    expected_size: Option<u64>,
+    range_upper_bound: Option<u64>,
+    consumed: u64,
+    inner: R,
+}
+
+impl<R: oio::Read> oio::Read for SanityCheckReader<R> {
+    async fn read(&mut self) -> Result<Buffer> {
+        let buf = self.inner.read().await?;
+
+        if buf.is_empty() {
+            // EOF: a short read is only a problem if the service told us
+            // upfront exactly how many bytes to expect. A bounded range
+            // alone doesn't make a shorter stream wrong.
+            if let Some(expected) = self.expected_size {
+                if self.consumed < expected {
+                    return Err(unexpected_response(
+                        &self.info,
+                        Operation::Read,
+                        &self.path,
+                        &self.path,
+                        format!(
+                            "reader ended after {} bytes but {expected} were expected",
+                            self.consumed
+                        ),
+                    ));
+                }
+            }
+            return Ok(buf);
+        }
+
+        self.consumed += buf.len() as u64;
+        if let Some(expected) = self.expected_size {
+            if self.consumed > expected {
+                return Err(unexpected_response(
+                    &self.info,
+                    Operation::Read,
+                    &self.path,
+                    &self.path,
+                    format!(
+                        "reader yielded {} bytes but only {expected} were expected",
+                        self.consumed
+                    ),
+                ));
+            }
+        } else if let Some(upper_bound) = self.range_upper_bound {
+            if self.consumed > upper_bound {
+                return Err(unexpected_response(
+                    &self.info,
+                    Operation::Read,
+                    &self.path,
+                    &self.path,
+                    format!(
+                        "reader yielded {} bytes but the requested range allows at most {upper_bound}",
+                        self.consumed
+                    ),
+                ));
+            }
+        }
+
+        Ok(buf)
+    }
+}
+
 pub struct SanityCheckLister<L> {
     info: Arc<AccessorInfo>,
     list_path: String,
@@ -119,6 +222,7 @@ impl<L: oio::List> oio::List for SanityCheckLister<L> {
     async fn next(&mut self) -> Result<Option<oio::Entry>> {
         match self.inner.next().await? {
             Some(entry) => {
+                sanity_check_path_containment(self.info.as_ref(), &self.list_path, entry.path())?;
                 sanity_check_path_mode(
                     self.info.as_ref(),
                     Operation::List,
@@ -133,6 +237,66 @@ impl<L: oio::List> oio::List for SanityCheckLister<L> {
     }
 }
 
+/// Guard against a service returning an entry that escapes the
+/// requested prefix, e.g. via a `../` component or an absolute path
+/// pointing outside of `list_path`. Callers commonly join listed paths
+/// onto a local filesystem path, so a malicious or buggy backend must
+/// not be able to smuggle a traversal through `list`.
+fn sanity_check_path_containment(
+    info: &AccessorInfo,
+    list_path: &str,
+    target_path: &str,
+) -> Result<()> {
+    for segment in target_path.split('/') {
+        if segment == ".." || segment == "." {
+            return Err(unexpected_response(
+                info,
+                Operation::List,
+                target_path,
+                list_path,
+                format!("path `{target_path}` contains a `{segment}` path segment"),
+            ));
+        }
+    }
+
+    if target_path.starts_with('/') && target_path != "/" {
+        return Err(unexpected_response(
+            info,
+            Operation::List,
+            target_path,
+            list_path,
+            format!("path `{target_path}` is absolute"),
+        ));
+    }
+
+    // A raw `starts_with` would accept `foobar/evil` as "contained" under
+    // `foo`, since it only compares bytes and never checks that the match
+    // ends on a path separator. Normalize `list_path` to end in `/` before
+    // comparing so the match can only land on a real path boundary. The
+    // root (`/`) contains every relative path, so it needs no prefix.
+    let boundary = if list_path == "/" {
+        String::new()
+    } else if list_path.is_empty() || list_path.ends_with('/') {
+        list_path.to_string()
+    } else {
+        format!("{list_path}/")
+    };
+
+    if target_path != list_path.trim_end_matches('/') && !target_path.starts_with(&boundary) {
+        return Err(unexpected_response(
+            info,
+            Operation::List,
+            target_path,
+            list_path,
+            format!(
+                "path `{target_path}` does not start with the requested prefix `{list_path}`"
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
 fn sanity_check_path_mode(
     info: &AccessorInfo,
     op: Operation,
@@ -272,4 +436,148 @@ mod tests {
             .expect_err("root marked as file should fail");
         assert_eq!(err.kind(), ErrorKind::Unexpected);
     }
+
+    #[test]
+    fn sanity_check_containment_accepts_entry_within_prefix() {
+        let info = build_info();
+        sanity_check_path_containment(&info, "dir/", "dir/file")
+            .expect("entry within prefix should pass");
+    }
+
+    #[test]
+    fn sanity_check_containment_rejects_parent_traversal() {
+        let info = build_info();
+        let err = sanity_check_path_containment(&info, "dir/", "dir/../../etc/passwd")
+            .expect_err("`..` traversal should fail");
+        assert_eq!(err.kind(), ErrorKind::Unexpected);
+    }
+
+    #[test]
+    fn sanity_check_containment_rejects_embedded_current_dir() {
+        let info = build_info();
+        let err = sanity_check_path_containment(&info, "dir/", "dir/./file")
+            .expect_err("embedded `.` should fail");
+        assert_eq!(err.kind(), ErrorKind::Unexpected);
+    }
+
+    #[test]
+    fn sanity_check_containment_rejects_path_outside_prefix() {
+        let info = build_info();
+        let err = sanity_check_path_containment(&info, "dir/", "other/file")
+            .expect_err("path outside prefix should fail");
+        assert_eq!(err.kind(), ErrorKind::Unexpected);
+    }
+
+    #[test]
+    fn sanity_check_containment_rejects_sibling_with_shared_prefix() {
+        let info = build_info();
+        let err = sanity_check_path_containment(&info, "foo", "foobar/evil")
+            .expect_err("a sibling directory sharing a string prefix should not be contained");
+        assert_eq!(err.kind(), ErrorKind::Unexpected);
+    }
+
+    #[test]
+    fn sanity_check_containment_accepts_prefix_without_trailing_slash() {
+        let info = build_info();
+        sanity_check_path_containment(&info, "foo", "foo/bar")
+            .expect("a real child of the prefix should still pass");
+    }
+
+    struct MockReader {
+        chunks: std::vec::IntoIter<Vec<u8>>,
+    }
+
+    impl MockReader {
+        fn new(chunks: Vec<Vec<u8>>) -> Self {
+            Self {
+                chunks: chunks.into_iter(),
+            }
+        }
+    }
+
+    impl oio::Read for MockReader {
+        async fn read(&mut self) -> Result<Buffer> {
+            Ok(self.chunks.next().map(Buffer::from).unwrap_or_default())
+        }
+    }
+
+    async fn drain(mut reader: SanityCheckReader<MockReader>) -> Result<u64> {
+        let mut total = 0;
+        loop {
+            let buf = reader.read().await?;
+            if buf.is_empty() {
+                break;
+            }
+            total += buf.len() as u64;
+        }
+        Ok(total)
+    }
+
+    fn build_reader(chunks: Vec<Vec<u8>>, expected_size: Option<u64>) -> SanityCheckReader<MockReader> {
+        build_reader_with_range(chunks, expected_size, None)
+    }
+
+    fn build_reader_with_range(
+        chunks: Vec<Vec<u8>>,
+        expected_size: Option<u64>,
+        range_upper_bound: Option<u64>,
+    ) -> SanityCheckReader<MockReader> {
+        SanityCheckReader {
+            info: Arc::new(build_info()),
+            path: "file".to_string(),
+            expected_size,
+            range_upper_bound,
+            consumed: 0,
+            inner: MockReader::new(chunks),
+        }
+    }
+
+    #[tokio::test]
+    async fn sanity_check_reader_accepts_matching_length() {
+        let reader = build_reader(vec![vec![0; 4], vec![0; 6]], Some(10));
+        let total = drain(reader).await.expect("matching length should pass");
+        assert_eq!(total, 10);
+    }
+
+    #[tokio::test]
+    async fn sanity_check_reader_rejects_short_read() {
+        let reader = build_reader(vec![vec![0; 4]], Some(10));
+        let err = drain(reader).await.expect_err("short read should fail");
+        assert_eq!(err.kind(), ErrorKind::Unexpected);
+    }
+
+    #[tokio::test]
+    async fn sanity_check_reader_rejects_overrun() {
+        let reader = build_reader(vec![vec![0; 4], vec![0; 10]], Some(10));
+        let err = drain(reader).await.expect_err("overrun should fail");
+        assert_eq!(err.kind(), ErrorKind::Unexpected);
+    }
+
+    #[tokio::test]
+    async fn sanity_check_reader_allows_unknown_length() {
+        let reader = build_reader(vec![vec![0; 4]], None);
+        let total = drain(reader).await.expect("unknown length should pass");
+        assert_eq!(total, 4);
+    }
+
+    #[tokio::test]
+    async fn sanity_check_reader_allows_range_clamped_shorter_than_requested() {
+        // RpRead::size is unknown and the range asked for 10 bytes, but the
+        // object only had 4 left, so the service correctly clamped the
+        // response. This must not be treated as a short read.
+        let reader = build_reader_with_range(vec![vec![0; 4]], None, Some(10));
+        let total = drain(reader)
+            .await
+            .expect("a range-clamped short response should pass when size is unknown");
+        assert_eq!(total, 4);
+    }
+
+    #[tokio::test]
+    async fn sanity_check_reader_rejects_overrun_past_range_upper_bound() {
+        let reader = build_reader_with_range(vec![vec![0; 4], vec![0; 10]], None, Some(10));
+        let err = drain(reader)
+            .await
+            .expect_err("yielding more than the requested range allows should fail");
+        assert_eq!(err.kind(), ErrorKind::Unexpected);
+    }
 }